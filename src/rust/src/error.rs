@@ -3,7 +3,11 @@
 // for complete details.
 
 pub enum CryptographyError {
-    Asn1Parse(asn1::ParseError),
+    // The `asn1` crate's `ParseError` only exposes `add_location`, not a
+    // getter for the location chain it accumulates, so we track our own
+    // copy of it here (populated in `add_location` below) to hand to
+    // Python as a structured value.
+    Asn1Parse(asn1::ParseError, Vec<String>),
     Asn1Write(asn1::WriteError),
     Py(pyo3::PyErr),
     OpenSSL(openssl::error::ErrorStack),
@@ -11,7 +15,7 @@ pub enum CryptographyError {
 
 impl From<asn1::ParseError> for CryptographyError {
     fn from(e: asn1::ParseError) -> CryptographyError {
-        CryptographyError::Asn1Parse(e)
+        CryptographyError::Asn1Parse(e, Vec::new())
     }
 }
 
@@ -48,12 +52,140 @@ impl From<pem::PemError> for CryptographyError {
     }
 }
 
+/// Well-known `(library, reason)` pairs decoded from an OpenSSL error that
+/// map onto a specific, catchable `cryptography.exceptions` (or builtin)
+/// type, rather than the generic `InternalError` catch-all. Matched against
+/// the topmost error on the stack, since that's where the actually
+/// actionable failure reason lives.
+///
+/// The library/reason strings here are the actual values
+/// `Error::library()`/`Error::reason()` decode to (i.e. what
+/// `ERR_lib_error_string`/`ERR_reason_error_string` return), not the
+/// `ERR_LIB_*` tokens -- e.g. `bad decrypt` is reported against
+/// `"digital envelope routines"` (EVP), not a `"cipher"` library.
+const KNOWN_OPENSSL_ERRORS: &[(&str, &str, &str, &str)] = &[
+    // (library, reason, python module, python exception)
+    ("rsa routines", "padding check failed", "builtins", "ValueError"),
+    ("rsa routines", "oaep decoding error", "builtins", "ValueError"),
+    (
+        "digital envelope routines",
+        "bad decrypt",
+        "builtins",
+        "ValueError",
+    ),
+    (
+        "dsa routines",
+        "bad signature",
+        "cryptography.exceptions",
+        "InvalidSignature",
+    ),
+    (
+        "ecdsa routines",
+        "bad signature",
+        "cryptography.exceptions",
+        "InvalidSignature",
+    ),
+    (
+        "rsa routines",
+        "bad signature",
+        "cryptography.exceptions",
+        "InvalidSignature",
+    ),
+    (
+        "x509 certificate routines",
+        "certificate verify failed",
+        "builtins",
+        "ValueError",
+    ),
+];
+
+/// With OpenSSL 3.0, algorithms live in providers (`default`, `legacy`,
+/// `fips`), and attempting one that isn't loaded surfaces as a `"fetch
+/// failed"` or `"unsupported"` reason from the EVP, provider or DSO
+/// libraries -- decoded by `Error::library()` as `"digital envelope
+/// routines"`/`"provider routines"`/`"DSO support routines"`, not the bare
+/// `ERR_LIB_EVP`/`ERR_LIB_PROV`/`ERR_LIB_DSO` tokens.
+fn is_provider_unsupported_error(library: Option<&str>, reason: Option<&str>) -> bool {
+    let library = library.unwrap_or("").to_ascii_lowercase();
+    let reason = reason.unwrap_or("").to_ascii_lowercase();
+    matches!(
+        library.as_str(),
+        "digital envelope routines" | "provider routines" | "dso support routines"
+    ) && (reason.contains("unsupported") || reason.contains("fetch failed"))
+}
+
+fn lookup_known_openssl_error(
+    library: Option<&str>,
+    reason: Option<&str>,
+) -> Option<(&'static str, &'static str)> {
+    let library = library?.to_ascii_lowercase();
+    let reason = reason?.to_ascii_lowercase();
+    KNOWN_OPENSSL_ERRORS
+        .iter()
+        .find(|(lib, why, _, _)| *lib == library && *why == reason)
+        .map(|(_, _, module, exc)| (*module, *exc))
+}
+
+/// Attaches the decoded OpenSSL error stack to `instance` as `.errors` (so
+/// it's reachable programmatically instead of only interpolated into the
+/// message), and chains a compact identifier of the specific error that
+/// triggered this conversion as `__cause__` -- distinct from the full list
+/// already attached -- so callers can introspect what actually failed in
+/// OpenSSL rather than regex-parsing our text.
+fn openssl_py_err_with_cause(
+    py: pyo3::Python<'_>,
+    instance: &pyo3::PyAny,
+    errors: &pyo3::types::PyList,
+    top: Option<&openssl::error::Error>,
+) -> pyo3::PyErr {
+    instance
+        .setattr("errors", errors)
+        .expect("Failed to set errors");
+    let py_err = pyo3::PyErr::from_instance(instance);
+    if let Some(top) = top {
+        py_err.set_cause(
+            py,
+            Some(pyo3::exceptions::PyRuntimeError::new_err(format!(
+                "OpenSSL error {:#x} ({}: {})",
+                top.code(),
+                top.library().unwrap_or("unknown"),
+                top.reason().unwrap_or("unknown"),
+            ))),
+        );
+    }
+    py_err
+}
+
 impl From<CryptographyError> for pyo3::PyErr {
     fn from(e: CryptographyError) -> pyo3::PyErr {
         match e {
-            CryptographyError::Asn1Parse(asn1_error) => pyo3::exceptions::PyValueError::new_err(
-                format!("error parsing asn1 value: {:?}", asn1_error),
-            ),
+            CryptographyError::Asn1Parse(asn1_error, locations) => {
+                let gil = pyo3::Python::acquire_gil();
+                let py = gil.python();
+
+                let py_err = pyo3::exceptions::PyValueError::new_err(format!(
+                    "error parsing asn1 value: {:?}",
+                    asn1_error
+                ));
+                // Keep the parse-location chain (accumulated via our own
+                // `add_location` as the error propagated up, since `asn1`
+                // doesn't expose a getter for the one it tracks internally)
+                // as a structured list on the exception instance, instead
+                // of only flattening it into the message -- so callers can
+                // introspect where parsing failed. Chain it as `__cause__`
+                // too, as a structured value distinct from the flat message
+                // above, rather than a duplicate of it.
+                let locations_list = pyo3::types::PyList::new(py, &locations);
+                py_err
+                    .value(py)
+                    .setattr("parse_locations", locations_list)
+                    .expect("Failed to set parse_locations");
+                py_err.set_cause(
+                    py,
+                    Some(pyo3::exceptions::PyValueError::new_err((locations_list,))),
+                );
+                py_err
+            }
             CryptographyError::Asn1Write(asn1::WriteError::AllocationError) => {
                 pyo3::exceptions::PyMemoryError::new_err(
                     "failed to allocate memory while performing ASN.1 serialization",
@@ -70,6 +202,27 @@ impl From<CryptographyError> for pyo3::PyErr {
                     .getattr(crate::intern!(py, "InternalError"))
                     .expect("Failed to get InternalError attribute");
 
+                if error_stack.errors().is_empty() {
+                    // We got here because some OpenSSL call returned a
+                    // failure code, but the error queue it should have
+                    // pushed a reason onto is empty. That's not "unknown
+                    // error" in the `InternalError` sense -- there's no
+                    // error detail to wrap, known or otherwise -- it's a
+                    // sign the queue was dirty (see the `InternalError`
+                    // message below) or the
+                    // call's return value doesn't actually mean failure, so
+                    // raise a distinct, narrower exception rather than
+                    // dressing up an empty list as an `InternalError`.
+                    return pyo3::exceptions::PyRuntimeError::new_err(
+                        "Unknown OpenSSL error. This error occurred without an \
+                         OpenSSL error queue entry, which usually indicates a \
+                         bug in this library or in OpenSSL itself. Please file \
+                         an issue at \
+                         https://github.com/pyca/cryptography/issues with \
+                         information on how to reproduce this.",
+                    );
+                }
+
                 let binding_mod = py
                     .import("cryptography.hazmat.bindings.openssl.binding")
                     .expect("Failed to import cryptography module");
@@ -86,39 +239,124 @@ impl From<CryptographyError> for pyo3::PyErr {
                     let err = openssl_error
                         .call_method1("from_code", (e.code(),))
                         .expect("Failed to call from_code");
+                    let err_with_text = openssl_error_with_text
+                        .call_method1("from_err", (err,))
+                        .expect("Failed to call from_err");
+                    // `_OpenSSLErrorWithText` is an immutable namedtuple, so
+                    // the human-readable mnemonics OpenSSL decodes for us
+                    // (e.g. library "SSL routines", reason "certificate
+                    // verify failed") can't be bolted onto it after the
+                    // fact -- attempting to `setattr` them raises. Carry
+                    // them alongside in a small record instead; the records
+                    // are reachable through the exception's `.errors`
+                    // list, not through its `str()`.
+                    let decoded_error = pyo3::types::PyDict::new(py);
+                    decoded_error
+                        .set_item("error", err_with_text)
+                        .expect("Failed to set error");
+                    decoded_error
+                        .set_item("lib", e.library())
+                        .expect("Failed to set lib");
+                    decoded_error
+                        .set_item("reason", e.reason())
+                        .expect("Failed to set reason");
+                    decoded_error
+                        .set_item("function", e.function())
+                        .expect("Failed to set function");
 
                     errors
-                        .append(
-                            openssl_error_with_text
-                                .call_method1("from_err", (err,))
-                                .expect("Failed to call from_err"),
-                        )
+                        .append(decoded_error)
                         .expect("List append failed");
                 }
-                pyo3::PyErr::from_instance(
-                    internal_error
+
+                // The topmost error is the one that's actually actionable;
+                // if we recognize it, raise the precise exception callers
+                // already expect instead of the generic `InternalError`.
+                let top = error_stack.errors().first();
+                if top
+                    .map(|e| is_provider_unsupported_error(e.library(), e.reason()))
+                    .unwrap_or(false)
+                {
+                    let unsupported_algorithm = py
+                        .import("cryptography.exceptions")
+                        .expect("Failed to import cryptography module")
+                        .getattr(crate::intern!(py, "UnsupportedAlgorithm"))
+                        .expect("Failed to get UnsupportedAlgorithm attribute");
+                    let instance = unsupported_algorithm
                         .call1((
-                            "Unknown OpenSSL error. This error is commonly encountered
+                            "This algorithm is not supported by the current OpenSSL \
+                             provider configuration. It may need the `legacy` provider \
+                             loaded (e.g. via OPENSSL_CONF) to be available.",
+                        ))
+                        .expect("Failed to create UnsupportedAlgorithm");
+                    return openssl_py_err_with_cause(py, instance, errors, top);
+                }
+                if let Some((module, exc_name)) =
+                    top.and_then(|e| lookup_known_openssl_error(e.library(), e.reason()))
+                {
+                    let exc_type = py
+                        .import(module)
+                        .expect("Failed to import exception module")
+                        .getattr(exc_name)
+                        .expect("Failed to get exception attribute");
+                    let instance = exc_type
+                        .call1((top.unwrap().reason().unwrap_or("unknown error"),))
+                        .expect("Failed to create exception");
+                    return openssl_py_err_with_cause(py, instance, errors, top);
+                }
+
+                // `InternalError.__init__` takes the error list as its second
+                // positional argument, so it is passed here as well as being
+                // attached as `errors` by `openssl_py_err_with_cause` below.
+                let instance = internal_error
+                    .call1((
+                        "Unknown OpenSSL error. This error is commonly encountered
                     when another library is not cleaning up the OpenSSL error
                     stack. If you are using cryptography with another library
                     that uses OpenSSL try disabling it before reporting a bug.
                     Otherwise please file an issue at
                     https://github.com/pyca/cryptography/issues with
                     information on how to reproduce this.",
-                            errors,
-                        ))
-                        .expect("Failed to create InternalError"),
-                )
+                        errors,
+                    ))
+                    .expect("Failed to create InternalError");
+                openssl_py_err_with_cause(py, instance, errors, top)
             }
         }
     }
 }
 
+/// Runs `f`, guaranteeing that whatever it returns is paired with exactly
+/// the OpenSSL errors `f` itself caused, not ones left over from some
+/// unrelated earlier call that never cleaned up after itself.
+///
+/// `openssl::error::ErrorStack::get()` drains the current thread's error
+/// queue as it reads it, so "snapshot" here means taking and discarding
+/// whatever was already on the queue before `f` runs (those stale entries
+/// are not pushed back afterward, so this is drain-before / diff-after
+/// rather than a true save-and-restore), and the `ErrorStack`
+/// returned alongside `f`'s result is the diff: only what accumulated
+/// during the call.
+///
+/// This file is a trimmed snapshot and doesn't contain the call sites
+/// (e.g. the hazmat symmetric-cipher and RSA bindings) that would wrap
+/// their OpenSSL FFI calls with this; it's exercised directly by
+/// `test_with_clean_error_queue` below instead.
+#[allow(dead_code)]
+pub(crate) fn with_clean_error_queue<T>(f: impl FnOnce() -> T) -> (T, openssl::error::ErrorStack) {
+    let _ = openssl::error::ErrorStack::get();
+    let result = f();
+    (result, openssl::error::ErrorStack::get())
+}
+
 impl CryptographyError {
     pub(crate) fn add_location(self, loc: asn1::ParseLocation) -> Self {
         match self {
             CryptographyError::Py(e) => CryptographyError::Py(e),
-            CryptographyError::Asn1Parse(e) => CryptographyError::Asn1Parse(e.add_location(loc)),
+            CryptographyError::Asn1Parse(e, mut locations) => {
+                locations.push(format!("{:?}", loc));
+                CryptographyError::Asn1Parse(e.add_location(loc), locations)
+            }
             CryptographyError::Asn1Write(e) => CryptographyError::Asn1Write(e),
             CryptographyError::OpenSSL(e) => CryptographyError::OpenSSL(e),
         }
@@ -132,7 +370,8 @@ pub(crate) type CryptographyResult<T = pyo3::PyObject> = Result<T, CryptographyE
 
 #[cfg(test)]
 mod tests {
-    use super::CryptographyError;
+    use super::{is_provider_unsupported_error, lookup_known_openssl_error, CryptographyError};
+    use pyo3::types::{PyDict, PyList};
 
     #[test]
     fn test_cryptographyerror_from() {
@@ -152,6 +391,70 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_lookup_known_openssl_error() {
+        // Matched against the real mnemonics `Error::library()`/`.reason()`
+        // decode to, not the bare `ERR_LIB_*` tokens.
+        assert_eq!(
+            lookup_known_openssl_error(Some("RSA routines"), Some("bad signature")),
+            Some(("cryptography.exceptions", "InvalidSignature")),
+        );
+        assert_eq!(
+            lookup_known_openssl_error(
+                Some("digital envelope routines"),
+                Some("Bad Decrypt"),
+            ),
+            Some(("builtins", "ValueError")),
+        );
+        assert_eq!(
+            lookup_known_openssl_error(Some("SSL routines"), Some("unknown")),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_is_provider_unsupported_error() {
+        assert!(is_provider_unsupported_error(
+            Some("digital envelope routines"),
+            Some("unsupported"),
+        ));
+        assert!(is_provider_unsupported_error(
+            Some("Provider routines"),
+            Some("Fetch failed"),
+        ));
+        assert!(!is_provider_unsupported_error(
+            Some("RSA routines"),
+            Some("bad signature"),
+        ));
+        assert!(!is_provider_unsupported_error(None, None));
+    }
+
+    #[test]
+    fn test_openssl_error_carries_decoded_mnemonics() {
+        pyo3::prepare_freethreaded_python();
+        pyo3::Python::with_gil(|py| {
+            let stack = openssl::x509::X509::from_pem(b"not a certificate").unwrap_err();
+            let py_err: pyo3::PyErr = CryptographyError::from(stack).into();
+
+            let errors = py_err
+                .value(py)
+                .getattr("errors")
+                .expect("Failed to get errors")
+                .downcast::<PyList>()
+                .expect("errors should be a list");
+            assert!(!errors.is_empty());
+
+            let first = errors
+                .get_item(0)
+                .downcast::<PyDict>()
+                .expect("error entry should carry decoded mnemonics");
+            assert!(first.contains("error").unwrap());
+            assert!(first.contains("lib").unwrap());
+            assert!(first.contains("reason").unwrap());
+            assert!(first.contains("function").unwrap());
+        })
+    }
+
     #[test]
     fn test_cryptographyerror_add_location() {
         let py_err = pyo3::PyErr::new::<pyo3::exceptions::PyValueError, _>("Error!");
@@ -164,4 +467,23 @@ mod tests {
         let openssl_error = openssl::error::ErrorStack::get();
         CryptographyError::from(openssl_error).add_location(asn1::ParseLocation::Field("meh"));
     }
+
+    #[test]
+    fn test_with_clean_error_queue() {
+        let (result, diff) = super::with_clean_error_queue(|| {
+            // The safe wrapper drains the queue into the `ErrorStack` it
+            // returns; push those errors back so they are left on the queue
+            // the way a failed raw FFI call leaves them.
+            openssl::x509::X509::from_pem(b"not a certificate")
+                .unwrap_err()
+                .put();
+            42
+        });
+        assert_eq!(result, 42);
+        assert!(!diff.errors().is_empty());
+
+        let (result, diff) = super::with_clean_error_queue(|| 7);
+        assert_eq!(result, 7);
+        assert!(diff.errors().is_empty());
+    }
 }